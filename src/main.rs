@@ -1,6 +1,17 @@
-use std::{io::BufRead, net::SocketAddr};
+use std::{
+    collections::HashMap,
+    io::{BufRead, Seek},
+    net::{IpAddr, SocketAddr},
+    os::unix::fs::MetadataExt,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
 
-use prometheus::{default_registry, register_int_counter_vec, Encoder, TextEncoder};
+use prometheus::{
+    core::Collector, default_registry, register_histogram, register_int_counter,
+    register_int_counter_vec, register_int_gauge, Encoder, TextEncoder,
+};
 use serde::{Deserialize, Serialize};
 use structopt::StructOpt;
 
@@ -14,6 +25,363 @@ struct Opt {
     /// events matching these addresses.
     #[structopt(long)]
     our_addresses: Vec<String>,
+
+    /// Path to a MaxMind GeoIP/ASN `.mmdb` database. When set, block metrics
+    /// are enriched with `country` and `asn` labels derived from the
+    /// validator's IP address.
+    #[structopt(long)]
+    geoip_db: Option<PathBuf>,
+
+    /// Path to a SQLite database used to persist counter state across
+    /// restarts. When set, `monad_proposed_blocks`, `monad_skipped_blocks`
+    /// and `monad_finalized_blocks` are restored from this file on startup
+    /// and periodically flushed to it, so `rate()` doesn't spike to zero
+    /// every time the process restarts.
+    #[structopt(long)]
+    state_db: Option<PathBuf>,
+
+    /// Where to read ledger log lines from: `stdin` (default), or
+    /// `file:<path>` to follow-tail a (possibly rotating) log file, for
+    /// running as a sidecar against `ledger_tail` output.
+    #[structopt(long, default_value = "stdin")]
+    input: InputSource,
+}
+
+/// Where `parse_input` reads `LogEntry` lines from.
+#[derive(Debug, Clone)]
+enum InputSource {
+    Stdin,
+    File(PathBuf),
+}
+
+impl std::str::FromStr for InputSource {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_prefix("file:") {
+            Some(path) => Ok(InputSource::File(PathBuf::from(path))),
+            None if s == "stdin" => Ok(InputSource::Stdin),
+            None => Err(format!(
+                "invalid --input {s:?}, expected \"stdin\" or \"file:<path>\""
+            )),
+        }
+    }
+}
+
+/// A source of raw log lines. Abstracts over stdin and a tailed log file so
+/// `parse_input` can share one code path regardless of where lines come from.
+trait LineSource {
+    /// Returns the next line (without its trailing newline), or `None` at a
+    /// clean EOF. Tailing sources block instead of returning `None`.
+    fn next_line(&mut self) -> std::io::Result<Option<String>>;
+}
+
+struct StdinSource {
+    stdin: std::io::Stdin,
+}
+
+impl StdinSource {
+    fn new() -> Self {
+        Self {
+            stdin: std::io::stdin(),
+        }
+    }
+}
+
+impl LineSource for StdinSource {
+    fn next_line(&mut self) -> std::io::Result<Option<String>> {
+        let mut line = String::new();
+        if self.stdin.lock().read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        if line.ends_with('\n') {
+            line.pop();
+            if line.ends_with('\r') {
+                line.pop();
+            }
+        }
+        Ok(Some(line))
+    }
+}
+
+/// How often a `FileTailSource` checks whether the file it's following has
+/// been rotated or truncated.
+const ROTATION_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How long to sleep between polls once a `FileTailSource` catches up to EOF.
+const TAIL_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Follow-tails a log file, transparently reopening it from the start when
+/// the file is rotated (inode changes) or truncated (shrinks).
+struct FileTailSource {
+    path: PathBuf,
+    reader: std::io::BufReader<std::fs::File>,
+    inode: u64,
+    offset: u64,
+    last_rotation_check: Instant,
+}
+
+impl FileTailSource {
+    fn open(path: PathBuf) -> std::io::Result<Self> {
+        let mut file = std::fs::File::open(&path)?;
+        let inode = file.metadata()?.ino();
+        let offset = file.seek(std::io::SeekFrom::End(0))?;
+        Ok(Self {
+            path,
+            reader: std::io::BufReader::new(file),
+            inode,
+            offset,
+            last_rotation_check: Instant::now(),
+        })
+    }
+
+    fn reopen_from_start(&mut self) -> std::io::Result<()> {
+        let file = std::fs::File::open(&self.path)?;
+        self.inode = file.metadata()?.ino();
+        self.offset = 0;
+        self.reader = std::io::BufReader::new(file);
+        Ok(())
+    }
+
+    /// Checks whether `path` has been rotated or truncated and reopens it
+    /// from the start if so, returning whether it did. Transient errors
+    /// (e.g. the file briefly missing mid-rotation) are logged and retried
+    /// on the next check rather than killing the tailer.
+    fn check_for_rotation(&mut self) -> bool {
+        if self.last_rotation_check.elapsed() < ROTATION_CHECK_INTERVAL {
+            return false;
+        }
+        self.last_rotation_check = Instant::now();
+
+        let metadata = match std::fs::metadata(&self.path) {
+            Ok(metadata) => metadata,
+            Err(e) => {
+                eprintln!("Failed to stat {:?}, will retry: {e}", self.path);
+                return false;
+            }
+        };
+        if metadata.ino() != self.inode || metadata.len() < self.offset {
+            eprintln!(
+                "Detected rotation/truncation of {:?}, reopening from start",
+                self.path
+            );
+            if let Err(e) = self.reopen_from_start() {
+                eprintln!("Failed to reopen {:?}, will retry: {e}", self.path);
+                return false;
+            }
+            return true;
+        }
+        false
+    }
+}
+
+impl LineSource for FileTailSource {
+    fn next_line(&mut self) -> std::io::Result<Option<String>> {
+        let mut line = String::new();
+        loop {
+            if self.check_for_rotation() {
+                // Whatever we'd buffered belonged to the old file instance.
+                line.clear();
+            }
+
+            let n = match self.reader.read_line(&mut line) {
+                Ok(n) => n,
+                Err(e) => {
+                    eprintln!("Failed to read from {:?}, will retry: {e}", self.path);
+                    std::thread::sleep(TAIL_POLL_INTERVAL);
+                    continue;
+                }
+            };
+            self.offset += n as u64;
+
+            // The writer may not have flushed a trailing `\n` yet; hold the
+            // partial line back rather than parsing a truncated record.
+            if n == 0 || !line.ends_with('\n') {
+                std::thread::sleep(TAIL_POLL_INTERVAL);
+                continue;
+            }
+
+            line.pop();
+            if line.ends_with('\r') {
+                line.pop();
+            }
+            return Ok(Some(line));
+        }
+    }
+}
+
+/// Label names shared by `monad_proposed_blocks`, `monad_skipped_blocks`,
+/// `monad_finalized_blocks`, `monad_timeouts` and `monad_block_transactions`.
+const AUTHOR_LABELS: &[&str] = &[
+    "author",
+    "author_dns",
+    "author_address",
+    "operated_by_us",
+    "ip",
+    "port",
+    "country",
+    "asn",
+];
+
+/// Interval at which persisted counter state is flushed to the state db.
+const STATE_DB_FLUSH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Persists the value of every label combination of a handful of
+/// `IntCounterVec`s to SQLite, so they survive process restarts.
+struct StateDb {
+    conn: rusqlite::Connection,
+}
+
+impl StateDb {
+    fn open(path: &Path) -> rusqlite::Result<Self> {
+        let conn = rusqlite::Connection::open(path)?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS counter_state (
+                metric TEXT NOT NULL,
+                labels TEXT NOT NULL,
+                value INTEGER NOT NULL,
+                PRIMARY KEY (metric, labels)
+            )",
+            [],
+        )?;
+        Ok(Self { conn })
+    }
+
+    fn load(&self, metric: &str) -> rusqlite::Result<Vec<(Vec<String>, i64)>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT labels, value FROM counter_state WHERE metric = ?1")?;
+        let rows = stmt
+            .query_map([metric], |row| {
+                let labels: String = row.get(0)?;
+                let value: i64 = row.get(1)?;
+                Ok((labels, value))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(rows
+            .into_iter()
+            .map(|(labels, value)| {
+                (
+                    serde_json::from_str::<Vec<String>>(&labels).unwrap_or_default(),
+                    value,
+                )
+            })
+            .collect())
+    }
+
+    fn save(&self, metric: &str, labels: &[String], value: i64) -> rusqlite::Result<()> {
+        let labels = serde_json::to_string(labels).expect("labels are always valid JSON");
+        self.conn.execute(
+            "INSERT INTO counter_state (metric, labels, value) VALUES (?1, ?2, ?3)
+             ON CONFLICT(metric, labels) DO UPDATE SET value = excluded.value",
+            rusqlite::params![metric, labels, value],
+        )?;
+        Ok(())
+    }
+}
+
+/// Reads the current value of every label combination of `vec` and upserts it
+/// into `state_db` under `metric`, in `AUTHOR_LABELS` order.
+fn flush_counter_state(state_db: &StateDb, metric: &str, vec: &prometheus::IntCounterVec) {
+    for family in vec.collect() {
+        for entry in family.get_metric() {
+            let labels: Vec<String> = AUTHOR_LABELS
+                .iter()
+                .map(|label_name| {
+                    entry
+                        .get_label()
+                        .iter()
+                        .find(|pair| pair.get_name() == *label_name)
+                        .map(|pair| pair.get_value().to_string())
+                        .unwrap_or_default()
+                })
+                .collect();
+            let value = entry.get_counter().get_value() as i64;
+            if let Err(e) = state_db.save(metric, &labels, value) {
+                eprintln!("Failed to persist {metric} state: {e}");
+            }
+        }
+    }
+}
+
+/// Resolves `country` and `asn` labels for an `IpAddr` from a MaxMind `.mmdb` database.
+///
+/// Built against the `maxminddb` 0.24/0.25 API, where `Reader::lookup` returns
+/// `Result<T, MaxMindDbError>` (not `Result<Option<T>>`); pin `maxminddb = "0.24"`
+/// when a `Cargo.toml` is introduced, or update the `.ok()` chains below for 0.26+.
+struct GeoIp {
+    reader: maxminddb::Reader<Vec<u8>>,
+}
+
+impl GeoIp {
+    fn open(path: &Path) -> std::io::Result<Self> {
+        let reader = maxminddb::Reader::open_readfile(path).map_err(std::io::Error::other)?;
+        Ok(Self { reader })
+    }
+
+    /// Looks up `country` and `asn` labels for `ip`, returning empty strings for
+    /// whichever (if any) the database doesn't have a record for.
+    fn lookup(&self, ip: IpAddr) -> (String, String) {
+        let country = self
+            .reader
+            .lookup::<maxminddb::geoip2::City>(ip)
+            .ok()
+            .and_then(|city| city.country)
+            .and_then(|country| country.iso_code)
+            .unwrap_or_default()
+            .to_string();
+        let asn = self
+            .reader
+            .lookup::<maxminddb::geoip2::Asn>(ip)
+            .ok()
+            .and_then(|asn| asn.autonomous_system_number)
+            .map(|asn| asn.to_string())
+            .unwrap_or_default();
+        (country, asn)
+    }
+}
+
+/// Splits an `ip:port` address (as seen in `author_address`/`author_dns`) into
+/// validated `(ip, port)` label values, optionally enriched with `(country,
+/// asn)` from `geoip`. Returns empty strings for any component that's missing
+/// or fails to parse, matching the existing "empty label on missing data"
+/// convention.
+fn resolve_address_labels(
+    address: Option<&str>,
+    geoip: Option<&GeoIp>,
+) -> (String, String, String, String) {
+    let (ip, port) = address
+        .and_then(|address| address.rsplit_once(':'))
+        .and_then(|(ip, port)| ip.parse::<IpAddr>().ok().map(|ip| (ip, port)))
+        .map_or((None, ""), |(ip, port)| (Some(ip), port));
+    let port = if port.parse::<u16>().is_ok() { port } else { "" };
+
+    let (country, asn) = ip
+        .zip(geoip)
+        .map(|(ip, geoip)| geoip.lookup(ip))
+        .unwrap_or_default();
+
+    (
+        ip.map(|ip| ip.to_string()).unwrap_or_default(),
+        port.to_string(),
+        country,
+        asn,
+    )
+}
+
+/// Pure round-gap transition used by `observe_round`: given the current tip
+/// (`None` until a round has ever been observed) and a newly observed round,
+/// returns the new tip and the gap count to add to `monad_round_gap_total`.
+///
+/// - First observation: adopts the round as the tip, no gap counted.
+/// - New round above the tip: gap is `round - tip - 1`, tip advances.
+/// - Round at or below the tip (out-of-order/duplicate): no-op.
+fn round_gap_step(tip: Option<u64>, round: u64) -> (Option<u64>, u64) {
+    match tip {
+        Some(tip) if round > tip => (Some(round), round - tip - 1),
+        Some(tip) => (Some(tip), 0),
+        None => (Some(round), 0),
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -74,7 +442,14 @@ fn main() -> std::io::Result<()> {
     let addr: SocketAddr = opt.listen_addr.parse().expect("Invalid listen-addr");
 
     let jh = std::thread::spawn(move || serve(&addr));
-    let sh = std::thread::spawn(move || parse_stdin(&opt.our_addresses));
+    let sh = std::thread::spawn(move || {
+        parse_input(
+            &opt.our_addresses,
+            opt.geoip_db.as_deref(),
+            opt.state_db.as_deref(),
+            opt.input,
+        )
+    });
 
     jh.join().unwrap();
     sh.join().unwrap().unwrap();
@@ -82,20 +457,96 @@ fn main() -> std::io::Result<()> {
     Ok(())
 }
 
-fn parse_stdin(our_addresses: &[String]) -> std::io::Result<()> {
+fn parse_input(
+    our_addresses: &[String],
+    geoip_db: Option<&Path>,
+    state_db: Option<&Path>,
+    input: InputSource,
+) -> std::io::Result<()> {
     println!("Parsing metrics, our addresses are {our_addresses:?}");
+
+    let geoip = geoip_db.and_then(|path| match GeoIp::open(path) {
+        Ok(geoip) => Some(geoip),
+        Err(e) => {
+            eprintln!("Failed to open GeoIP database {path:?}: {e}");
+            None
+        }
+    });
+
     let proposed_blocks = register_int_counter_vec!(
         "monad_proposed_blocks",
         "Number of proposed blocks by author.",
-        &["author", "author_dns", "author_address", "operated_by_us"]
+        AUTHOR_LABELS
     )
     .unwrap();
     let skipped_blocks = register_int_counter_vec!(
         "monad_skipped_blocks",
         "Number of skipped blocks by author.",
-        &["author", "author_dns", "author_address", "operated_by_us"]
+        AUTHOR_LABELS
+    )
+    .unwrap();
+    let finalized_blocks = register_int_counter_vec!(
+        "monad_finalized_blocks",
+        "Number of finalized blocks by author.",
+        AUTHOR_LABELS
     )
     .unwrap();
+    let timeouts = register_int_counter_vec!(
+        "monad_timeouts",
+        "Number of consensus timeouts by author.",
+        AUTHOR_LABELS
+    )
+    .unwrap();
+    let block_transactions = register_int_counter_vec!(
+        "monad_block_transactions",
+        "Number of transactions in proposed blocks by author.",
+        AUTHOR_LABELS
+    )
+    .unwrap();
+    let current_epoch = register_int_gauge!(
+        "monad_current_epoch",
+        "Epoch of the most recently finalized block."
+    )
+    .unwrap();
+
+    // (metric name, counter vec) pairs whose state is persisted to `--state-db`.
+    let persisted_counters = [
+        ("monad_proposed_blocks", proposed_blocks.clone()),
+        ("monad_skipped_blocks", skipped_blocks.clone()),
+        ("monad_finalized_blocks", finalized_blocks.clone()),
+    ];
+
+    let state_db = state_db.map(|path| {
+        let db = StateDb::open(path).expect("Unable to open state db");
+        for (metric, vec) in &persisted_counters {
+            match db.load(metric) {
+                Ok(rows) => {
+                    for (labels, value) in rows {
+                        if value <= 0 || labels.len() != AUTHOR_LABELS.len() {
+                            continue;
+                        }
+                        let labels: Vec<&str> = labels.iter().map(String::as_str).collect();
+                        vec.with_label_values(&labels).inc_by(value as u64);
+                    }
+                }
+                Err(e) => eprintln!("Failed to reload {metric} state: {e}"),
+            }
+        }
+        Arc::new(Mutex::new(db))
+    });
+
+    // Detached: dies with the process. A final synchronous flush happens
+    // below once stdin closes.
+    if let Some(state_db) = state_db.clone() {
+        let persisted_counters = persisted_counters.clone();
+        std::thread::spawn(move || loop {
+            std::thread::sleep(STATE_DB_FLUSH_INTERVAL);
+            let state_db = state_db.lock().unwrap();
+            for (metric, vec) in &persisted_counters {
+                flush_counter_state(&state_db, metric, vec);
+            }
+        });
+    }
 
     let read_lines = register_int_counter_vec!(
         "monad_ledger_exporter_lines_parsed",
@@ -107,10 +558,66 @@ fn parse_stdin(our_addresses: &[String]) -> std::io::Result<()> {
     read_lines.with_label_values(&["success"]).reset();
     read_lines.with_label_values(&["failure"]).reset();
 
-    let stdin = std::io::stdin();
-    for line in stdin.lock().lines() {
-        let line = line?;
+    const LATENCY_BUCKETS: &[f64] = &[50.0, 100.0, 200.0, 400.0, 800.0, 1600.0, 3200.0];
+
+    let block_propagation_ms = register_histogram!(
+        "monad_block_propagation_ms",
+        "Milliseconds between a block's timestamp and when we observed its proposal.",
+        LATENCY_BUCKETS.to_vec()
+    )
+    .unwrap();
+    let finalization_latency_ms = register_histogram!(
+        "monad_finalization_latency_ms",
+        "Milliseconds between a block's proposal and its finalization.",
+        LATENCY_BUCKETS.to_vec()
+    )
+    .unwrap();
+    let finalization_unmatched = register_int_counter!(
+        "monad_finalization_unmatched",
+        "Number of finalized blocks whose matching proposal was never observed."
+    )
+    .unwrap();
+
+    // seq_num -> now_ts_ms at proposal time, drained on the matching finalization.
+    let mut pending_proposals: HashMap<String, u64> = HashMap::new();
+    const SEQ_NUM_EVICTION_WINDOW: u64 = 10_000;
+    let mut max_seq_num: u64 = 0;
+
+    let round_gap_total = register_int_counter!(
+        "monad_round_gap_total",
+        "Number of consensus rounds that produced no log line at all."
+    )
+    .unwrap();
+    let highest_round_gauge = register_int_gauge!(
+        "monad_highest_round",
+        "Highest consensus round observed so far."
+    )
+    .unwrap();
+
+    // Highest round observed so far, used to detect gaps. `None` until the first
+    // round is seen so we don't count a bogus gap from 0 to the first real round.
+    let mut highest_round: Option<u64> = None;
+    let mut observe_round = |round: &str| {
+        let Ok(round) = round.parse::<u64>() else {
+            return;
+        };
+        let (new_tip, gap) = round_gap_step(highest_round, round);
+        if gap > 0 {
+            round_gap_total.inc_by(gap);
+        }
+        if new_tip != highest_round {
+            highest_round = new_tip;
+            let tip = new_tip.expect("round_gap_step only ever advances the tip");
+            highest_round_gauge.set(tip as i64);
+        }
+    };
+
+    let mut input: Box<dyn LineSource> = match input {
+        InputSource::Stdin => Box::new(StdinSource::new()),
+        InputSource::File(path) => Box::new(FileTailSource::open(path)?),
+    };
 
+    while let Some(line) = input.next_line()? {
         if line.trim().is_empty() {
             continue;
         }
@@ -120,63 +627,175 @@ fn parse_stdin(our_addresses: &[String]) -> std::io::Result<()> {
                 read_lines.with_label_values(&["success"]).inc();
                 match log_entry.fields {
                     LogFields::ProposedBlock {
+                        round,
                         author,
                         author_dns,
                         author_address,
+                        seq_num,
+                        now_ts_ms,
+                        block_ts_ms,
+                        num_tx,
                         ..
                     } => {
+                        observe_round(&round);
                         let operated_by_us: &str = if our_addresses.contains(&author) {
                             "true"
                         } else {
                             "false"
                         };
+                        let (ip, port, country, asn) = resolve_address_labels(
+                            author_address.as_deref().or(author_dns.as_deref()),
+                            geoip.as_ref(),
+                        );
                         proposed_blocks
                             .with_label_values(&[
                                 author.as_str(),
                                 author_dns.as_deref().unwrap_or(""),
                                 author_address.as_deref().unwrap_or(""),
                                 operated_by_us,
+                                ip.as_str(),
+                                port.as_str(),
+                                country.as_str(),
+                                asn.as_str(),
                             ])
                             .inc();
+
+                        if let Ok(num_tx) = num_tx.parse::<u64>() {
+                            block_transactions
+                                .with_label_values(&[
+                                    author.as_str(),
+                                    author_dns.as_deref().unwrap_or(""),
+                                    author_address.as_deref().unwrap_or(""),
+                                    operated_by_us,
+                                    ip.as_str(),
+                                    port.as_str(),
+                                    country.as_str(),
+                                    asn.as_str(),
+                                ])
+                                .inc_by(num_tx);
+                        }
+
+                        if let Ok(now_ts_ms) = now_ts_ms.parse::<u64>() {
+                            if let Ok(block_ts_ms) = block_ts_ms.parse::<u64>() {
+                                block_propagation_ms
+                                    .observe(now_ts_ms.saturating_sub(block_ts_ms) as f64);
+                            }
+
+                            if let Ok(seq_num_val) = seq_num.parse::<u64>() {
+                                max_seq_num = max_seq_num.max(seq_num_val);
+                                pending_proposals.insert(seq_num, now_ts_ms);
+                            }
+                        }
+
+                        pending_proposals.retain(|seq, _| {
+                            seq.parse::<u64>().map_or(true, |seq_num_val| {
+                                max_seq_num.saturating_sub(seq_num_val) <= SEQ_NUM_EVICTION_WINDOW
+                            })
+                        });
                     }
                     LogFields::SkippedBlock {
+                        round,
                         author,
                         author_dns,
                         author_address,
                         ..
                     } => {
+                        observe_round(&round);
                         let operated_by_us: &str = if our_addresses.contains(&author) {
                             "true"
                         } else {
                             "false"
                         };
+                        let (ip, port, country, asn) = resolve_address_labels(
+                            author_address.as_deref().or(author_dns.as_deref()),
+                            geoip.as_ref(),
+                        );
                         skipped_blocks
                             .with_label_values(&[
                                 author.as_str(),
                                 author_dns.as_deref().unwrap_or(""),
                                 author_address.as_deref().unwrap_or(""),
                                 operated_by_us,
+                                ip.as_str(),
+                                port.as_str(),
+                                country.as_str(),
+                                asn.as_str(),
                             ])
                             .inc();
                     }
-                    LogFields::FinalizedBlock { .. } => {}
+                    LogFields::FinalizedBlock {
+                        round,
+                        author,
+                        author_dns,
+                        author_address,
+                        epoch,
+                        seq_num,
+                        now_ts_ms,
+                        ..
+                    } => {
+                        observe_round(&round);
+                        let operated_by_us: &str = if our_addresses.contains(&author) {
+                            "true"
+                        } else {
+                            "false"
+                        };
+                        let (ip, port, country, asn) = resolve_address_labels(
+                            author_address.as_deref().or(author_dns.as_deref()),
+                            geoip.as_ref(),
+                        );
+                        finalized_blocks
+                            .with_label_values(&[
+                                author.as_str(),
+                                author_dns.as_deref().unwrap_or(""),
+                                author_address.as_deref().unwrap_or(""),
+                                operated_by_us,
+                                ip.as_str(),
+                                port.as_str(),
+                                country.as_str(),
+                                asn.as_str(),
+                            ])
+                            .inc();
+
+                        if let Ok(epoch) = epoch.parse::<i64>() {
+                            current_epoch.set(epoch);
+                        }
+
+                        if let Some(proposed_ts) = pending_proposals.remove(&seq_num) {
+                            if let Ok(now_ts_ms) = now_ts_ms.parse::<u64>() {
+                                finalization_latency_ms
+                                    .observe(now_ts_ms.saturating_sub(proposed_ts) as f64);
+                            }
+                        } else {
+                            finalization_unmatched.inc();
+                        }
+                    }
                     LogFields::Timeout {
+                        round,
                         author,
                         author_dns,
                         author_address,
                         ..
                     } => {
+                        observe_round(&round);
                         let operated_by_us: &str = if our_addresses.contains(&author) {
                             "true"
                         } else {
                             "false"
                         };
-                        skipped_blocks
+                        let (ip, port, country, asn) = resolve_address_labels(
+                            author_address.as_deref().or(author_dns.as_deref()),
+                            geoip.as_ref(),
+                        );
+                        timeouts
                             .with_label_values(&[
                                 author.as_str(),
                                 author_dns.as_deref().unwrap_or(""),
                                 author_address.as_deref().unwrap_or(""),
                                 operated_by_us,
+                                ip.as_str(),
+                                port.as_str(),
+                                country.as_str(),
+                                asn.as_str(),
                             ])
                             .inc();
                     }
@@ -189,6 +808,14 @@ fn parse_stdin(our_addresses: &[String]) -> std::io::Result<()> {
             }
         }
     }
+
+    if let Some(state_db) = &state_db {
+        let state_db = state_db.lock().unwrap();
+        for (metric, vec) in &persisted_counters {
+            flush_counter_state(&state_db, metric, vec);
+        }
+    }
+
     Ok(())
 }
 
@@ -301,4 +928,68 @@ mod tests {
             _ => panic!("expected SkippedBlock"),
         }
     }
+
+    #[test]
+    fn test_input_source_from_str() {
+        assert!(matches!("stdin".parse(), Ok(InputSource::Stdin)));
+        match "file:/var/log/ledger_tail.log".parse::<InputSource>() {
+            Ok(InputSource::File(path)) => {
+                assert_eq!(path, std::path::PathBuf::from("/var/log/ledger_tail.log"))
+            }
+            other => panic!("expected InputSource::File, got {other:?}"),
+        }
+        assert!("bogus".parse::<InputSource>().is_err());
+    }
+
+    #[test]
+    fn test_resolve_address_labels() {
+        let (ip, port, country, asn) = resolve_address_labels(Some("84.32.220.55:8000"), None);
+        assert_eq!(ip, "84.32.220.55");
+        assert_eq!(port, "8000");
+        assert_eq!(country, "");
+        assert_eq!(asn, "");
+
+        let (ip, port, country, asn) = resolve_address_labels(None, None);
+        assert_eq!(ip, "");
+        assert_eq!(port, "");
+        assert_eq!(country, "");
+        assert_eq!(asn, "");
+
+        // Not a valid IP, so the whole address is treated as missing.
+        let (ip, port, country, asn) = resolve_address_labels(Some("not-an-ip:8000"), None);
+        assert_eq!(ip, "");
+        assert_eq!(port, "");
+        assert_eq!(country, "");
+        assert_eq!(asn, "");
+    }
+
+    #[test]
+    fn test_round_gap_step() {
+        // First observation adopts the round as the tip without counting a gap.
+        assert_eq!(round_gap_step(None, 100), (Some(100), 0));
+
+        // A later round with no gap (consecutive).
+        assert_eq!(round_gap_step(Some(100), 101), (Some(101), 0));
+
+        // A later round with missing rounds in between.
+        assert_eq!(round_gap_step(Some(100), 105), (Some(105), 4));
+
+        // Out-of-order/duplicate rounds at or below the tip are ignored.
+        assert_eq!(round_gap_step(Some(105), 105), (Some(105), 0));
+        assert_eq!(round_gap_step(Some(105), 42), (Some(105), 0));
+    }
+
+    #[test]
+    fn test_state_db_round_trip() {
+        let db = StateDb::open(std::path::Path::new(":memory:")).unwrap();
+
+        let labels = vec!["alice".to_string(), "".to_string(), "".to_string()];
+        db.save("monad_proposed_blocks", &labels, 41).unwrap();
+        db.save("monad_proposed_blocks", &labels, 42).unwrap();
+
+        let rows = db.load("monad_proposed_blocks").unwrap();
+        assert_eq!(rows, vec![(labels, 42)]);
+
+        assert_eq!(db.load("monad_skipped_blocks").unwrap(), vec![]);
+    }
 }